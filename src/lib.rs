@@ -1,5 +1,7 @@
 #![doc = include_str!("../README.md")]
 
+mod qoi;
+
 use image::imageops::{colorops::grayscale, FilterType};
 use image::{DynamicImage, GenericImageView};
 use palette::{IntoColor, Lab, Srgb};
@@ -37,15 +39,9 @@ fn get_shade_block(brightness: u8) -> char {
     }
 }
 
-fn get_structured_block(
-    pixels: &[Vec<[u8; 4]>],
-    x: usize,
-    y: usize,
-    width: usize,
-    height: usize,
-) -> char {
-    if y < height && x < width {
-        let pix = pixels[y][x];
+fn get_structured_block(pixels: &PixelGrid, x: usize, y: usize) -> char {
+    if y < pixels.height() && x < pixels.width() {
+        let pix = pixels.pixel(x, y);
         let brightness = rgb_to_brightness(pix[0], pix[1], pix[2]);
         get_shade_block(brightness)
     } else {
@@ -64,6 +60,350 @@ fn cielab_distance(r1: u8, g1: u8, b1: u8, r2: u8, g2: u8, b2: u8) -> f32 {
     db.mul_add(db, dl.mul_add(dl, da * da)).sqrt()
 }
 
+fn srgb_to_lab(r: u8, g: u8, b: u8) -> Lab {
+    let color: Srgb<u8> = Srgb::new(r, g, b);
+    color.into_linear::<f32>().into_color()
+}
+
+fn lab_to_srgb(lab: Lab) -> (u8, u8, u8) {
+    let srgb: Srgb = lab.into_color();
+    let srgb: Srgb<u8> = srgb.into_format();
+    (srgb.red, srgb.green, srgb.blue)
+}
+
+struct ColorBox {
+    points: Vec<Lab>,
+}
+
+impl ColorBox {
+    fn channel_range(&self, channel: usize) -> f32 {
+        let mut min = f32::MAX;
+        let mut max = f32::MIN;
+        for point in &self.points {
+            let value = match channel {
+                0 => point.l,
+                1 => point.a,
+                _ => point.b,
+            };
+            min = min.min(value);
+            max = max.max(value);
+        }
+        max - min
+    }
+
+    fn longest_axis(&self) -> usize {
+        let ranges = [
+            self.channel_range(0),
+            self.channel_range(1),
+            self.channel_range(2),
+        ];
+        let mut longest = 0;
+        for axis in 1..ranges.len() {
+            if ranges[axis] > ranges[longest] {
+                longest = axis;
+            }
+        }
+        longest
+    }
+
+    fn longest_axis_range(&self) -> f32 {
+        self.channel_range(self.longest_axis())
+    }
+
+    fn split(mut self) -> (Self, Self) {
+        let axis = self.longest_axis();
+        self.points.sort_by(|p1, p2| {
+            let v1 = match axis {
+                0 => p1.l,
+                1 => p1.a,
+                _ => p1.b,
+            };
+            let v2 = match axis {
+                0 => p2.l,
+                1 => p2.a,
+                _ => p2.b,
+            };
+            v1.total_cmp(&v2)
+        });
+        let mid = self.points.len() / 2;
+        let right = self.points.split_off(mid);
+        (Self { points: self.points }, Self { points: right })
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn centroid(&self) -> Lab {
+        let count = self.points.len() as f32;
+        let (mut l, mut a, mut b) = (0.0, 0.0, 0.0);
+        for point in &self.points {
+            l += point.l;
+            a += point.a;
+            b += point.b;
+        }
+        Lab::new(l / count, a / count, b / count)
+    }
+}
+
+fn build_palette(
+    pixels: &PixelGrid,
+    palette_size: usize,
+    alpha_threshold: u8,
+) -> Vec<(u8, u8, u8)> {
+    let points: Vec<Lab> = pixels
+        .data()
+        .iter()
+        .filter(|pix| pix[3] >= alpha_threshold)
+        .map(|pix| srgb_to_lab(pix[0], pix[1], pix[2]))
+        .collect();
+
+    if points.is_empty() || palette_size == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![ColorBox { points }];
+    while boxes.len() < palette_size {
+        let Some((index, _)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.points.len() > 1)
+            .max_by(|(_, a), (_, b)| a.longest_axis_range().total_cmp(&b.longest_axis_range()))
+        else {
+            break;
+        };
+        let box_to_split = boxes.remove(index);
+        let (left, right) = box_to_split.split();
+        boxes.push(left);
+        boxes.push(right);
+    }
+
+    let mut centroids: Vec<Lab> = boxes.iter().map(ColorBox::centroid).collect();
+
+    const EPSILON: f32 = 0.1;
+    for _ in 0..5 {
+        let mut sums = vec![(0.0f32, 0.0f32, 0.0f32, 0u32); centroids.len()];
+        for pix in pixels.data() {
+            if pix[3] < alpha_threshold {
+                continue;
+            }
+            let lab = srgb_to_lab(pix[0], pix[1], pix[2]);
+            let mut best = 0;
+            let mut best_dist = f32::MAX;
+            for (index, centroid) in centroids.iter().enumerate() {
+                let dist = cielab_point_distance(lab, *centroid);
+                if dist < best_dist {
+                    best_dist = dist;
+                    best = index;
+                }
+            }
+            let entry = &mut sums[best];
+            entry.0 += lab.l;
+            entry.1 += lab.a;
+            entry.2 += lab.b;
+            entry.3 += 1;
+        }
+
+        let mut moved = 0.0f32;
+        #[allow(clippy::cast_precision_loss)]
+        for (centroid, (sl, sa, sb, count)) in centroids.iter_mut().zip(sums) {
+            if count == 0 {
+                continue;
+            }
+            let count = count as f32;
+            let new_centroid = Lab::new(sl / count, sa / count, sb / count);
+            moved = moved.max(cielab_point_distance(*centroid, new_centroid));
+            *centroid = new_centroid;
+        }
+
+        if moved < EPSILON {
+            break;
+        }
+    }
+
+    centroids.into_iter().map(lab_to_srgb).collect()
+}
+
+fn cielab_point_distance(lab1: Lab, lab2: Lab) -> f32 {
+    let dl = lab1.l - lab2.l;
+    let da = lab1.a - lab2.a;
+    let db = lab1.b - lab2.b;
+    db.mul_add(db, dl.mul_add(dl, da * da)).sqrt()
+}
+
+fn nearest_palette_color(r: u8, g: u8, b: u8, palette: &[(u8, u8, u8)]) -> (u8, u8, u8) {
+    let mut best = (r, g, b);
+    let mut best_dist = f32::MAX;
+    for &(pr, pg, pb) in palette {
+        let dist = cielab_distance(r, g, b, pr, pg, pb);
+        if dist < best_dist {
+            best_dist = dist;
+            best = (pr, pg, pb);
+        }
+    }
+    best
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorMode {
+    TrueColor,
+    Indexed256,
+    /// Known deviation: bright variants are emitted as aixterm SGR codes
+    /// (90-97 foreground, 100-107 background, see `fg_sgr`/`bg_sgr`) rather
+    /// than the standard `ESC[3Xm`/`ESC[4Xm` plus SGR 1 (bold). Most modern
+    /// terminals support the aixterm range, but it's not part of ECMA-48 --
+    /// a terminal that only understands the literal 16-color spec plus bold
+    /// will not render these as bright.
+    Ansi16,
+}
+
+const ANSI16_COLORS: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+const XTERM_CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+fn xterm256_index(r: u8, g: u8, b: u8) -> u8 {
+    let mut best_index = 16;
+    let mut best_dist = f32::MAX;
+
+    for (ri, &rv) in XTERM_CUBE_LEVELS.iter().enumerate() {
+        for (gi, &gv) in XTERM_CUBE_LEVELS.iter().enumerate() {
+            for (bi, &bv) in XTERM_CUBE_LEVELS.iter().enumerate() {
+                let dist = cielab_distance(r, g, b, rv, gv, bv);
+                if dist < best_dist {
+                    best_dist = dist;
+                    #[allow(clippy::cast_possible_truncation)]
+                    {
+                        best_index = 16 + (36 * ri + 6 * gi + bi) as u8;
+                    }
+                }
+            }
+        }
+    }
+
+    for step in 0..24u8 {
+        let level = 10 * step + 8;
+        let dist = cielab_distance(r, g, b, level, level, level);
+        if dist < best_dist {
+            best_dist = dist;
+            best_index = 232 + step;
+        }
+    }
+
+    best_index
+}
+
+fn ansi16_index(r: u8, g: u8, b: u8) -> (u8, bool) {
+    let mut best_index = 0;
+    let mut best_dist = f32::MAX;
+
+    for (index, &(cr, cg, cb)) in ANSI16_COLORS.iter().enumerate() {
+        let dist = cielab_distance(r, g, b, cr, cg, cb);
+        if dist < best_dist {
+            best_dist = dist;
+            best_index = index;
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    {
+        (best_index as u8 % 8, best_index >= 8)
+    }
+}
+
+fn color_slot(mode: ColorMode, r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    match mode {
+        ColorMode::TrueColor => (r, g, b),
+        ColorMode::Indexed256 => (xterm256_index(r, g, b), 0, 0),
+        ColorMode::Ansi16 => {
+            let (digit, bright) = ansi16_index(r, g, b);
+            (digit, u8::from(bright), 0)
+        }
+    }
+}
+
+fn xterm256_to_rgb(index: u8) -> (u8, u8, u8) {
+    if index >= 232 {
+        let level = 10 * (index - 232) + 8;
+        (level, level, level)
+    } else {
+        let idx = usize::from(index - 16);
+        let (ri, gi, bi) = (idx / 36, (idx % 36) / 6, idx % 6);
+        (XTERM_CUBE_LEVELS[ri], XTERM_CUBE_LEVELS[gi], XTERM_CUBE_LEVELS[bi])
+    }
+}
+
+fn ansi16_to_rgb(digit: u8, bright: bool) -> (u8, u8, u8) {
+    let index = usize::from(digit) + if bright { 8 } else { 0 };
+    ANSI16_COLORS[index]
+}
+
+/// Snaps a color to the RGB value `mode` will actually render it as, i.e.
+/// the same reduction `fg_sgr`/`bg_sgr` perform. Used so dithering diffuses
+/// error against the real rendered color instead of the unreduced source
+/// pixel, which is what actually causes banding in indexed color modes.
+fn color_mode_rgb(mode: ColorMode, r: u8, g: u8, b: u8) -> (u8, u8, u8) {
+    match mode {
+        ColorMode::TrueColor => (r, g, b),
+        ColorMode::Indexed256 => xterm256_to_rgb(xterm256_index(r, g, b)),
+        ColorMode::Ansi16 => {
+            let (digit, bright) = ansi16_index(r, g, b);
+            ansi16_to_rgb(digit, bright)
+        }
+    }
+}
+
+fn fg_sgr(mode: ColorMode, r: u8, g: u8, b: u8) -> String {
+    match mode {
+        ColorMode::TrueColor => format!("38;2;{r};{g};{b}"),
+        ColorMode::Indexed256 => format!("38;5;{}", xterm256_index(r, g, b)),
+        ColorMode::Ansi16 => {
+            let (digit, bright) = ansi16_index(r, g, b);
+            if bright {
+                // SGR 1 (bold) only brightens foreground text in *some*
+                // terminals and leaves stray bold state active for whatever
+                // comes after it in the same run (see `bg_sgr`'s aixterm
+                // note) -- use the aixterm bright-foreground codes (90-97)
+                // instead, matching the background's approach.
+                format!("9{digit}")
+            } else {
+                format!("3{digit}")
+            }
+        }
+    }
+}
+
+fn bg_sgr(mode: ColorMode, r: u8, g: u8, b: u8) -> String {
+    match mode {
+        ColorMode::TrueColor => format!("48;2;{r};{g};{b}"),
+        ColorMode::Indexed256 => format!("48;5;{}", xterm256_index(r, g, b)),
+        ColorMode::Ansi16 => {
+            let (digit, bright) = ansi16_index(r, g, b);
+            if bright {
+                // SGR 1 (bold) only brightens foreground text in real
+                // terminals, not backgrounds -- use the aixterm extended
+                // bright-background codes (100-107) instead.
+                format!("10{digit}")
+            } else {
+                format!("4{digit}")
+            }
+        }
+    }
+}
+
 fn quantize_color(
     r: u8,
     g: u8,
@@ -107,7 +447,18 @@ impl Image {
     ///
     /// Returns an error if the image file cannot be opened or decoded.
     pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, image::ImageError> {
-        let img = image::open(path)?;
+        let path = path.as_ref();
+        let is_qoi = path
+            .extension()
+            .and_then(std::ffi::OsStr::to_str)
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("qoi"));
+
+        let img = if is_qoi {
+            let bytes = std::fs::read(path).map_err(image::ImageError::IoError)?;
+            qoi::decode(&bytes)?
+        } else {
+            image::open(path)?
+        };
         Ok(Self { inner: img })
     }
 
@@ -133,6 +484,264 @@ impl Image {
     }
 }
 
+/// A flat, row-major buffer of RGBA pixels produced by a [`Resampler`].
+///
+/// Using a single flat `Vec` instead of `Vec<Vec<_>>` avoids one allocation
+/// per row and lets every row be addressed with a single multiply-add.
+#[derive(Debug, Clone)]
+pub struct PixelGrid {
+    width: usize,
+    height: usize,
+    data: Vec<[u8; 4]>,
+}
+
+impl PixelGrid {
+    /// # Panics
+    ///
+    /// Panics if `data.len()` does not equal `width * height`.
+    #[must_use]
+    pub fn new(width: usize, height: usize, data: Vec<[u8; 4]>) -> Self {
+        assert_eq!(
+            data.len(),
+            width * height,
+            "pixel data length must match width * height"
+        );
+        Self { width, height, data }
+    }
+
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[must_use]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[must_use]
+    pub fn data(&self) -> &[[u8; 4]] {
+        &self.data
+    }
+
+    #[must_use]
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.data[y * self.width + x]
+    }
+
+    fn pixel_mut(&mut self, x: usize, y: usize) -> &mut [u8; 4] {
+        &mut self.data[y * self.width + x]
+    }
+}
+
+/// A pluggable image-resize backend. Conversion code only ever sees the
+/// resulting [`PixelGrid`], so a new backend can be added without touching
+/// `convert_blocks_mode`/`convert_half_blocks_mode`.
+pub trait Resampler {
+    fn resample(&self, image: &DynamicImage, width: u32, height: u32) -> PixelGrid;
+}
+
+struct ImageCrateResampler {
+    filter: FilterType,
+    /// Stretch to the exact `width`/`height` instead of fitting within them
+    /// while preserving the source aspect ratio. Needed whenever the target
+    /// box already carries a deliberate aspect correction (e.g. `cell_aspect`),
+    /// since fitting-within would undo that correction by shrinking the other
+    /// axis to compensate.
+    exact: bool,
+}
+
+impl Resampler for ImageCrateResampler {
+    fn resample(&self, image: &DynamicImage, width: u32, height: u32) -> PixelGrid {
+        let resized = if self.exact {
+            image.resize_exact(width, height, self.filter)
+        } else {
+            image.resize(width, height, self.filter)
+        };
+        let w = resized.width() as usize;
+        let h = resized.height() as usize;
+        let mut data = vec![[0u8; 4]; w * h];
+        for (x, y, pix) in resized.pixels() {
+            data[y as usize * w + x as usize] = pix.0;
+        }
+        PixelGrid::new(w, h, data)
+    }
+}
+
+/// Kernel used by [`ConvolutionResampler`] for separable convolution resizing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConvolutionKernel {
+    Lanczos3,
+    CatmullRom,
+}
+
+impl ConvolutionKernel {
+    fn support(self) -> f32 {
+        match self {
+            Self::CatmullRom => 2.0,
+            Self::Lanczos3 => 3.0,
+        }
+    }
+
+    fn weight(self, x: f32) -> f32 {
+        match self {
+            Self::CatmullRom => catmull_rom_weight(x),
+            Self::Lanczos3 => lanczos_weight(x),
+        }
+    }
+}
+
+fn catmull_rom_weight(x: f32) -> f32 {
+    let x = x.abs();
+    if x < 1.0 {
+        1.5f32.mul_add(x * x * x, 1.0 - 2.5 * x * x)
+    } else if x < 2.0 {
+        (-0.5f32).mul_add(x * x * x, 2.5f32.mul_add(x * x, (-4.0f32).mul_add(x, 2.0)))
+    } else {
+        0.0
+    }
+}
+
+fn lanczos_weight(x: f32) -> f32 {
+    const A: f32 = 3.0;
+    if x == 0.0 {
+        1.0
+    } else if x.abs() < A {
+        let px = std::f32::consts::PI * x;
+        A * px.sin() * (px / A).sin() / (px * px)
+    } else {
+        0.0
+    }
+}
+
+/// Resamples a single axis (rows or columns) using `kernel`, sampling each
+/// source pixel through `get`. Downsampling widens the kernel support by the
+/// scale factor so high frequencies are band-limited instead of aliasing.
+fn resample_axis(
+    get: impl Fn(usize) -> [f32; 4],
+    src_len: usize,
+    dst_len: usize,
+    kernel: ConvolutionKernel,
+) -> Vec<[f32; 4]> {
+    if src_len == 0 || dst_len == 0 {
+        return Vec::new();
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    let scale = src_len as f32 / dst_len as f32;
+    let filter_scale = scale.max(1.0);
+    let support = kernel.support() * filter_scale;
+
+    let mut out = Vec::with_capacity(dst_len);
+    for dst_i in 0..dst_len {
+        #[allow(clippy::cast_precision_loss)]
+        let center = (dst_i as f32 + 0.5) * scale - 0.5;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let lo = (center - support).floor().max(0.0) as usize;
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let hi = (center + support).ceil().min((src_len - 1) as f32) as usize;
+
+        let mut sum = [0.0f32; 4];
+        let mut weight_sum = 0.0f32;
+        for src_i in lo..=hi {
+            #[allow(clippy::cast_precision_loss)]
+            let dist = (src_i as f32 - center) / filter_scale;
+            let weight = kernel.weight(dist);
+            if weight == 0.0 {
+                continue;
+            }
+            let pix = get(src_i);
+            for (c, channel) in sum.iter_mut().enumerate() {
+                *channel += pix[c] * weight;
+            }
+            weight_sum += weight;
+        }
+
+        if weight_sum.abs() > f32::EPSILON {
+            for channel in &mut sum {
+                *channel /= weight_sum;
+            }
+        }
+        out.push(sum);
+    }
+    out
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn clamp_pixel(pix: [f32; 4]) -> [u8; 4] {
+    [
+        pix[0].round().clamp(0.0, 255.0) as u8,
+        pix[1].round().clamp(0.0, 255.0) as u8,
+        pix[2].round().clamp(0.0, 255.0) as u8,
+        pix[3].round().clamp(0.0, 255.0) as u8,
+    ]
+}
+
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fit_within(src_w: usize, src_h: usize, max_w: usize, max_h: usize) -> (usize, usize) {
+    if src_w == 0 || src_h == 0 || max_w == 0 || max_h == 0 {
+        return (max_w.max(1), max_h.max(1));
+    }
+    let ratio = (max_w as f32 / src_w as f32).min(max_h as f32 / src_h as f32);
+    let w = ((src_w as f32) * ratio).round().max(1.0) as usize;
+    let h = ((src_h as f32) * ratio).round().max(1.0) as usize;
+    (w, h)
+}
+
+/// A SIMD-friendly slot: separable convolution resize with a Lanczos or
+/// Catmull-Rom kernel, applied to rows then columns.
+pub struct ConvolutionResampler {
+    pub kernel: ConvolutionKernel,
+    /// Stretch to the exact `width`/`height` instead of fitting within them
+    /// while preserving the source aspect ratio. See
+    /// [`ImageCrateResampler`]'s field of the same name.
+    pub exact: bool,
+}
+
+impl Resampler for ConvolutionResampler {
+    fn resample(&self, image: &DynamicImage, width: u32, height: u32) -> PixelGrid {
+        let rgba = image.to_rgba8();
+        let src_w = rgba.width() as usize;
+        let src_h = rgba.height() as usize;
+        let (dst_w, dst_h) = if self.exact {
+            (width as usize, height as usize)
+        } else {
+            fit_within(src_w, src_h, width as usize, height as usize)
+        };
+
+        let horiz: Vec<[f32; 4]> = (0..src_h)
+            .flat_map(|y| {
+                resample_axis(
+                    |x| {
+                        let p = rgba.get_pixel(x as u32, y as u32);
+                        [f32::from(p[0]), f32::from(p[1]), f32::from(p[2]), f32::from(p[3])]
+                    },
+                    src_w,
+                    dst_w,
+                    self.kernel,
+                )
+            })
+            .collect();
+
+        let mut data = vec![[0u8; 4]; dst_w * dst_h];
+        for x in 0..dst_w {
+            let column = resample_axis(|y| horiz[y * dst_w + x], src_h, dst_h, self.kernel);
+            for (y, pix) in column.into_iter().enumerate() {
+                data[y * dst_w + x] = clamp_pixel(pix);
+            }
+        }
+
+        PixelGrid::new(dst_w, dst_h, data)
+    }
+}
+
+/// Selects which built-in [`Resampler`] backend `resize_image` uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResampleBackend {
+    ImageCrate,
+    Convolution(ConvolutionKernel),
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct ConversionConfig {
     pub size: (usize, usize),
@@ -141,6 +750,17 @@ pub struct ConversionConfig {
     pub resize_filter: FilterType,
     pub color_tolerance: f32,
     pub use_blocks: bool,
+    pub palette_size: Option<usize>,
+    pub color_mode: ColorMode,
+    pub dither: bool,
+    pub background: Option<(u8, u8, u8)>,
+    pub resample_backend: ResampleBackend,
+    /// Overrides the cell-aspect correction factor normally derived from
+    /// `use_blocks`. Terminal cells are roughly twice as tall as they are
+    /// wide, but "roughly" varies by font, so callers that need a different
+    /// ratio than the 0.5/1.0 default can set this instead of getting
+    /// squashed/stretched output. `None` keeps the derived default.
+    pub cell_aspect: Option<f32>,
 }
 
 impl Default for ConversionConfig {
@@ -152,57 +772,285 @@ impl Default for ConversionConfig {
             resize_filter: FilterType::Nearest,
             color_tolerance: 0.0,
             use_blocks: false,
+            palette_size: None,
+            color_mode: ColorMode::TrueColor,
+            dither: false,
+            background: None,
+            resample_backend: ResampleBackend::ImageCrate,
+            cell_aspect: None,
         }
     }
 }
 
-fn resize_image(image: &DynamicImage, config: &ConversionConfig) -> Vec<Vec<[u8; 4]>> {
-    let mut pixels: Vec<Vec<[u8; 4]>> = vec![];
-    #[allow(clippy::cast_possible_truncation)]
-    {
-        let width = u32::try_from(config.size.0).unwrap_or(u32::MAX);
-        let height = u32::try_from(config.size.1).unwrap_or(u32::MAX);
-        for (x, y, pix) in image.resize(width, height, config.resize_filter).pixels() {
-            if x == 0 {
-                pixels.push(vec![]);
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn quantize_for_dither(
+    r: f32,
+    g: f32,
+    b: f32,
+    fixed_palette: Option<&[(u8, u8, u8)]>,
+    color_palette: &mut Vec<(u8, u8, u8)>,
+    tolerance: f32,
+    color_mode: ColorMode,
+) -> (u8, u8, u8) {
+    let ru = r.clamp(0.0, 255.0).round() as u8;
+    let gu = g.clamp(0.0, 255.0).round() as u8;
+    let bu = b.clamp(0.0, 255.0).round() as u8;
+
+    if let Some(palette) = fixed_palette {
+        let (pr, pg, pb) = nearest_palette_color(ru, gu, bu, palette);
+        if color_mode == ColorMode::TrueColor {
+            (pr, pg, pb)
+        } else {
+            // `fg_sgr`/`bg_sgr` reduce the palette color again to the
+            // nearest indexed-mode slot at render time, so dither against
+            // that final color, not the unreduced palette entry.
+            color_mode_rgb(color_mode, pr, pg, pb)
+        }
+    } else if color_mode != ColorMode::TrueColor {
+        // No fixed palette was requested, so the color reduction that's
+        // actually going to happen is the one `fg_sgr`/`bg_sgr` apply for
+        // this terminal color mode -- dither against that, not against an
+        // unrelated dynamic tolerance palette.
+        color_mode_rgb(color_mode, ru, gu, bu)
+    } else if tolerance > 0.0 {
+        quantize_color(ru, gu, bu, color_palette, tolerance)
+    } else {
+        (ru, gu, bu)
+    }
+}
+
+fn dither_pixels(
+    pixels: &mut PixelGrid,
+    config: &ConversionConfig,
+    fixed_palette: Option<&[(u8, u8, u8)]>,
+) {
+    let (width, height) = (pixels.width(), pixels.height());
+    if width == 0 || height == 0 {
+        return;
+    }
+
+    let mut buffer: Vec<[f32; 3]> = pixels
+        .data()
+        .iter()
+        .map(|pix| [f32::from(pix[0]), f32::from(pix[1]), f32::from(pix[2])])
+        .collect();
+    let mut color_palette: Vec<(u8, u8, u8)> = Vec::new();
+
+    for y in 0..height {
+        for x in 0..width {
+            if pixels.pixel(x, y)[3] < config.alpha_threshold {
+                continue;
+            }
+
+            let [r, g, b] = buffer[y * width + x];
+            let (nr, ng, nb) = quantize_for_dither(
+                r,
+                g,
+                b,
+                fixed_palette,
+                &mut color_palette,
+                config.color_tolerance,
+                config.color_mode,
+            );
+            let pix = pixels.pixel_mut(x, y);
+            pix[0] = nr;
+            pix[1] = ng;
+            pix[2] = nb;
+
+            let err = [r - f32::from(nr), g - f32::from(ng), b - f32::from(nb)];
+
+            if x + 1 < width && pixels.pixel(x + 1, y)[3] >= config.alpha_threshold {
+                for c in 0..3 {
+                    buffer[y * width + x + 1][c] += err[c] * (7.0 / 16.0);
+                }
+            }
+            if y + 1 < height {
+                if x > 0 && pixels.pixel(x - 1, y + 1)[3] >= config.alpha_threshold {
+                    for c in 0..3 {
+                        buffer[(y + 1) * width + x - 1][c] += err[c] * (3.0 / 16.0);
+                    }
+                }
+                if pixels.pixel(x, y + 1)[3] >= config.alpha_threshold {
+                    for c in 0..3 {
+                        buffer[(y + 1) * width + x][c] += err[c] * (5.0 / 16.0);
+                    }
+                }
+                if x + 1 < width && pixels.pixel(x + 1, y + 1)[3] >= config.alpha_threshold {
+                    for c in 0..3 {
+                        buffer[(y + 1) * width + x + 1][c] += err[c] * (1.0 / 16.0);
+                    }
+                }
             }
-            pixels[y as usize].push(pix.0);
         }
     }
-    pixels
 }
 
-fn convert_blocks_mode(pixels: &[Vec<[u8; 4]>], config: &ConversionConfig, esc: &str) -> String {
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn composite_background(pixels: &mut PixelGrid, background: (u8, u8, u8)) {
+    let bg = [background.0, background.1, background.2];
+    for pix in &mut pixels.data {
+        let alpha = f32::from(pix[3]) / 255.0;
+        for c in 0..3 {
+            let blended = f32::from(pix[c]).mul_add(alpha, f32::from(bg[c]) * (1.0 - alpha));
+            pix[c] = blended.clamp(0.0, 255.0).round() as u8;
+        }
+        pix[3] = 255;
+    }
+}
+
+/// Terminal cells are roughly twice as tall as they are wide. Full-block mode
+/// renders one pixel per cell, so the *content* sampled from the source image
+/// must be squashed vertically to compensate; half-block mode already packs
+/// two pixel rows into one cell and needs no correction. Deriving this from
+/// `use_blocks` here (rather than leaving callers to set a matching factor by
+/// hand) keeps the two in sync by default, while `config.cell_aspect` still
+/// lets a caller override it for fonts that aren't exactly 2:1.
+fn cell_aspect(config: &ConversionConfig) -> f32 {
+    config.cell_aspect.unwrap_or(if config.use_blocks { 0.5 } else { 1.0 })
+}
+
+fn height_cells(config: &ConversionConfig) -> u32 {
+    u32::try_from(config.size.1).unwrap_or(u32::MAX)
+}
+
+/// Centers `pixels` vertically within `target_height` rows: pads with fully
+/// transparent rows when the cell-aspect squash in [`resize_image`] left
+/// `pixels` shorter than requested (those rows render as blank space via the
+/// existing `alpha_threshold` check, or as the background color once
+/// `config.background` compositing makes them opaque), or crops symmetrically
+/// when it's taller
+/// (only possible with a `cell_aspect` override greater than 1.0). Keeping the
+/// row count this fixes up always equal to `target_height` is what lets
+/// `resize_image` honor `config.size.1` exactly.
+fn center_rows(pixels: PixelGrid, target_height: usize) -> PixelGrid {
+    let width = pixels.width();
+    let height = pixels.height();
+    if height == target_height {
+        return pixels;
+    }
+
+    let mut data = vec![[0u8; 4]; width * target_height];
+    if height < target_height {
+        let pad_top = (target_height - height) / 2;
+        for y in 0..height {
+            let dst_row = (y + pad_top) * width;
+            let src_row = y * width;
+            data[dst_row..dst_row + width]
+                .copy_from_slice(&pixels.data()[src_row..src_row + width]);
+        }
+    } else {
+        let crop_top = (height - target_height) / 2;
+        for y in 0..target_height {
+            let dst_row = y * width;
+            let src_row = (y + crop_top) * width;
+            data[dst_row..dst_row + width]
+                .copy_from_slice(&pixels.data()[src_row..src_row + width]);
+        }
+    }
+    PixelGrid::new(width, target_height, data)
+}
+
+fn resize_image(image: &DynamicImage, config: &ConversionConfig) -> PixelGrid {
+    let width = u32::try_from(config.size.0).unwrap_or(u32::MAX);
+    let height_cells = height_cells(config);
+    let aspect = cell_aspect(config);
+
+    if (aspect - 1.0).abs() <= f32::EPSILON {
+        // No cell-aspect correction needed: let the resampler fit the source
+        // aspect ratio within the requested box itself, same as before this
+        // correction existed.
+        return match config.resample_backend {
+            ResampleBackend::ImageCrate => {
+                ImageCrateResampler { filter: config.resize_filter, exact: false }
+                    .resample(image, width, height_cells)
+            }
+            ResampleBackend::Convolution(kernel) => {
+                ConvolutionResampler { kernel, exact: false }.resample(image, width, height_cells)
+            }
+        };
+    }
+
+    // Fit the source aspect ratio within the *uncorrected* box first, so the
+    // source's own proportions are preserved exactly as they would be without
+    // cell-aspect correction. Only then squash the fitted height by `aspect`
+    // and stretch to that already-fitted box — this undoes only what the
+    // cell-aspect compensation requires, instead of hard-stretching the whole
+    // image into a pre-squashed box and discarding its proportions outright.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let (fit_w, fit_h) = fit_within(
+        image.width() as usize,
+        image.height() as usize,
+        width as usize,
+        height_cells as usize,
+    );
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss, clippy::cast_precision_loss)]
+    let squashed_h = ((fit_h as f32) * aspect).round().max(1.0) as u32;
+    #[allow(clippy::cast_possible_truncation)]
+    let fit_w = fit_w as u32;
+
+    let resized = match config.resample_backend {
+        ResampleBackend::ImageCrate => ImageCrateResampler {
+            filter: config.resize_filter,
+            exact: true,
+        }
+        .resample(image, fit_w, squashed_h),
+        ResampleBackend::Convolution(kernel) => {
+            ConvolutionResampler { kernel, exact: true }.resample(image, fit_w, squashed_h)
+        }
+    };
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    center_rows(resized, height_cells as usize)
+}
+
+fn convert_blocks_mode(
+    pixels: &PixelGrid,
+    config: &ConversionConfig,
+    esc: &str,
+    fixed_palette: Option<&[(u8, u8, u8)]>,
+) -> String {
     let mut color_palette: Vec<(u8, u8, u8)> = Vec::new();
     let mut out = String::new();
 
-    for line in 0..pixels.len() {
+    for line in 0..pixels.height() {
         let mut last_fg: Option<(u8, u8, u8)> = None;
-        for char in 0..pixels[line].len() {
-            let mut pix: [u8; 4] = pixels[line][char];
-
-            if config.color_tolerance > 0.0 {
-                let (r, g, b) = quantize_color(
-                    pix[0],
-                    pix[1],
-                    pix[2],
-                    &mut color_palette,
-                    config.color_tolerance,
-                );
-                pix[0] = r;
-                pix[1] = g;
-                pix[2] = b;
+        for char in 0..pixels.width() {
+            let mut pix: [u8; 4] = pixels.pixel(char, line);
+
+            // When `dither` is on, `dither_pixels` already quantized every
+            // pixel against the fixed palette, tolerance palette, or
+            // color-mode reduction (whichever applies) while distributing
+            // error. Re-quantizing here would re-merge colors that
+            // dithering deliberately kept distinct.
+            if !config.dither {
+                if let Some(palette) = fixed_palette {
+                    let (r, g, b) = nearest_palette_color(pix[0], pix[1], pix[2], palette);
+                    pix[0] = r;
+                    pix[1] = g;
+                    pix[2] = b;
+                } else if config.color_tolerance > 0.0 {
+                    let (r, g, b) = quantize_color(
+                        pix[0],
+                        pix[1],
+                        pix[2],
+                        &mut color_palette,
+                        config.color_tolerance,
+                    );
+                    pix[0] = r;
+                    pix[1] = g;
+                    pix[2] = b;
+                }
             }
 
             if pix[3] < config.alpha_threshold {
                 out.push(' ');
                 last_fg = None;
             } else {
-                let block =
-                    get_structured_block(pixels, char, line, pixels[line].len(), pixels.len());
-                let current_fg = (pix[0], pix[1], pix[2]);
+                let block = get_structured_block(pixels, char, line);
+                let current_fg = color_slot(config.color_mode, pix[0], pix[1], pix[2]);
                 if last_fg != Some(current_fg) {
-                    write!(out, "{esc}[38;2;{};{};{}m", pix[0], pix[1], pix[2]).unwrap();
+                    write!(out, "{esc}[{}m", fg_sgr(config.color_mode, pix[0], pix[1], pix[2]))
+                        .unwrap();
                     last_fg = Some(current_fg);
                 }
                 out.push(block);
@@ -217,45 +1065,64 @@ fn convert_blocks_mode(pixels: &[Vec<[u8; 4]>], config: &ConversionConfig, esc:
 }
 
 fn convert_half_blocks_mode(
-    pixels: &[Vec<[u8; 4]>],
+    pixels: &PixelGrid,
     config: &ConversionConfig,
     esc: &str,
+    fixed_palette: Option<&[(u8, u8, u8)]>,
 ) -> String {
     let mut color_palette: Vec<(u8, u8, u8)> = Vec::new();
     let mut out = String::new();
 
-    for line in (0..pixels.len()).filter(|index| index % 2 == 0) {
-        for char in 0..pixels[line].len() {
-            let mut top_pix: [u8; 4] = pixels[line][char];
-            let mut bot_pix: [u8; 4] = if line + 1 >= pixels.len() {
+    for line in (0..pixels.height()).filter(|index| index % 2 == 0) {
+        for char in 0..pixels.width() {
+            let mut top_pix: [u8; 4] = pixels.pixel(char, line);
+            let mut bot_pix: [u8; 4] = if line + 1 >= pixels.height() {
                 [0; 4]
             } else {
-                pixels[line + 1][char]
+                pixels.pixel(char, line + 1)
             };
 
-            if config.color_tolerance > 0.0 {
-                let (r, g, b) = quantize_color(
-                    top_pix[0],
-                    top_pix[1],
-                    top_pix[2],
-                    &mut color_palette,
-                    config.color_tolerance,
-                );
-                top_pix[0] = r;
-                top_pix[1] = g;
-                top_pix[2] = b;
-
-                if bot_pix[3] >= config.alpha_threshold {
+            // See the matching comment in `convert_blocks_mode`: skip
+            // re-quantizing pixels that `dither_pixels` already reduced.
+            if !config.dither {
+                if let Some(palette) = fixed_palette {
+                    let (r, g, b) =
+                        nearest_palette_color(top_pix[0], top_pix[1], top_pix[2], palette);
+                    top_pix[0] = r;
+                    top_pix[1] = g;
+                    top_pix[2] = b;
+
+                    if bot_pix[3] >= config.alpha_threshold {
+                        let (r, g, b) =
+                            nearest_palette_color(bot_pix[0], bot_pix[1], bot_pix[2], palette);
+                        bot_pix[0] = r;
+                        bot_pix[1] = g;
+                        bot_pix[2] = b;
+                    }
+                } else if config.color_tolerance > 0.0 {
                     let (r, g, b) = quantize_color(
-                        bot_pix[0],
-                        bot_pix[1],
-                        bot_pix[2],
+                        top_pix[0],
+                        top_pix[1],
+                        top_pix[2],
                         &mut color_palette,
                         config.color_tolerance,
                     );
-                    bot_pix[0] = r;
-                    bot_pix[1] = g;
-                    bot_pix[2] = b;
+                    top_pix[0] = r;
+                    top_pix[1] = g;
+                    top_pix[2] = b;
+
+                    if bot_pix[3] >= config.alpha_threshold {
+                        let (r, g, b) = quantize_color(
+                            bot_pix[0],
+                            bot_pix[1],
+                            bot_pix[2],
+                            &mut color_palette,
+                            config.color_tolerance,
+                        );
+                        bot_pix[0] = r;
+                        bot_pix[1] = g;
+                        bot_pix[2] = b;
+                    }
                 }
             }
 
@@ -264,32 +1131,15 @@ fn convert_half_blocks_mode(
             if top_invis && bot_invis {
                 out.push(' ');
             } else if top_invis && !bot_invis {
-                write!(
-                    out,
-                    "{esc}[38;2;{};{};{}m{}{esc}[0m",
-                    bot_pix[0], bot_pix[1], bot_pix[2], BOTTOM_HALF
-                )
-                .unwrap();
+                let fg = fg_sgr(config.color_mode, bot_pix[0], bot_pix[1], bot_pix[2]);
+                write!(out, "{esc}[{fg}m{BOTTOM_HALF}{esc}[0m").unwrap();
             } else if !top_invis && bot_invis {
-                write!(
-                    out,
-                    "{esc}[38;2;{};{};{}m{}{esc}[0m",
-                    top_pix[0], top_pix[1], top_pix[2], TOP_HALF
-                )
-                .unwrap();
+                let fg = fg_sgr(config.color_mode, top_pix[0], top_pix[1], top_pix[2]);
+                write!(out, "{esc}[{fg}m{TOP_HALF}{esc}[0m").unwrap();
             } else {
-                write!(
-                    out,
-                    "{esc}[38;2;{};{};{};48;2;{};{};{}m{}{esc}[0m",
-                    bot_pix[0],
-                    bot_pix[1],
-                    bot_pix[2],
-                    top_pix[0],
-                    top_pix[1],
-                    top_pix[2],
-                    BOTTOM_HALF
-                )
-                .unwrap();
+                let fg = fg_sgr(config.color_mode, bot_pix[0], bot_pix[1], bot_pix[2]);
+                let bg = bg_sgr(config.color_mode, top_pix[0], top_pix[1], top_pix[2]);
+                write!(out, "{esc}[{fg};{bg}m{BOTTOM_HALF}{esc}[0m").unwrap();
             }
         }
         out.push('\n');
@@ -299,11 +1149,186 @@ fn convert_half_blocks_mode(
 
 fn convert_image(image: &DynamicImage, config: &ConversionConfig) -> String {
     let esc = if config.raw { "\\x1b" } else { "\x1b" };
-    let pixels = resize_image(image, config);
+    let mut pixels = resize_image(image, config);
+
+    if let Some(background) = config.background {
+        composite_background(&mut pixels, background);
+    }
+
+    let fixed_palette =
+        config.palette_size.map(|size| build_palette(&pixels, size, config.alpha_threshold));
+
+    if config.dither {
+        dither_pixels(&mut pixels, config, fixed_palette.as_deref());
+    }
 
     if config.use_blocks {
-        convert_blocks_mode(&pixels, config, esc)
+        convert_blocks_mode(&pixels, config, esc, fixed_palette.as_deref())
     } else {
-        convert_half_blocks_mode(&pixels, config, esc)
+        convert_half_blocks_mode(&pixels, config, esc, fixed_palette.as_deref())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blocks_mode_output_has_exactly_the_requested_row_count() {
+        let image = DynamicImage::new_rgba8(40, 40);
+        let config =
+            ConversionConfig { size: (10, 10), use_blocks: true, ..ConversionConfig::default() };
+
+        let out = convert_image(&image, &config);
+
+        assert_eq!(out.lines().count(), config.size.1);
+    }
+
+    #[test]
+    fn half_blocks_mode_does_not_letterbox_a_fit_within_shrunk_grid() {
+        // A very wide, short image forces `fit_within` to shrink the sampled
+        // grid well below the requested box to preserve its own aspect ratio.
+        // That pre-existing letterboxing is unrelated to the cell-aspect
+        // correction and must not be re-padded back out to `config.size.1`.
+        let image = DynamicImage::new_rgba8(200, 10);
+        let config =
+            ConversionConfig { size: (20, 20), use_blocks: false, ..ConversionConfig::default() };
+
+        let pixels = resize_image(&image, &config);
+
+        assert!(
+            pixels.height() < 20,
+            "expected fit-within to shrink height, got {}",
+            pixels.height()
+        );
+    }
+
+    #[test]
+    fn blocks_mode_fits_within_before_squashing_instead_of_stretching() {
+        // A wide, opaque image doesn't match the requested square box, so
+        // fitting it in (even after the full-block cell-aspect squash) must
+        // letterbox with transparent padding rather than hard-stretching the
+        // source to fill every requested row.
+        let image = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+            100,
+            50,
+            image::Rgba([255, 255, 255, 255]),
+        ));
+        let config =
+            ConversionConfig { size: (20, 20), use_blocks: true, ..ConversionConfig::default() };
+
+        let pixels = resize_image(&image, &config);
+
+        assert_eq!(pixels.height(), 20);
+        let top_row_opaque = (0..pixels.width()).any(|x| pixels.pixel(x, 0)[3] > 0);
+        assert!(
+            !top_row_opaque,
+            "expected transparent letterbox padding at the top, image was stretched to fill instead"
+        );
+    }
+
+    #[test]
+    fn build_palette_converges_to_requested_size_for_two_color_image() {
+        let data = vec![[255, 0, 0, 255]; 50]
+            .into_iter()
+            .chain(vec![[0, 0, 255, 255]; 50])
+            .collect();
+        let pixels = PixelGrid::new(10, 10, data);
+
+        let palette = build_palette(&pixels, 2, 128);
+
+        assert_eq!(palette.len(), 2);
+        let reds = palette.iter().filter(|&&(r, g, b)| r > g && r > b).count();
+        let blues = palette.iter().filter(|&&(r, g, b)| b > r && b > g).count();
+        assert_eq!((reds, blues), (1, 1));
+    }
+
+    #[test]
+    fn resample_axis_is_identity_at_equal_scale() {
+        let src = [[10.0, 20.0, 30.0, 255.0], [40.0, 50.0, 60.0, 255.0], [70.0, 80.0, 90.0, 255.0]];
+        let out = resample_axis(|i| src[i], src.len(), src.len(), ConvolutionKernel::CatmullRom);
+
+        for (got, want) in out.iter().zip(src.iter()) {
+            for c in 0..4 {
+                assert!((got[c] - want[c]).abs() < 1e-3, "{got:?} != {want:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn composite_background_blends_by_alpha_and_opaques_the_result() {
+        let mut pixels = PixelGrid::new(2, 1, vec![[255, 0, 0, 128], [0, 255, 0, 0]]);
+
+        composite_background(&mut pixels, (0, 0, 255));
+
+        assert_eq!(pixels.pixel(0, 0), [128, 0, 127, 255]);
+        assert_eq!(pixels.pixel(1, 0), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn ansi16_bright_colors_use_aixterm_sgr_codes() {
+        // Pure red is ANSI-16 index 9 (bright red, digit 1).
+        assert_eq!(fg_sgr(ColorMode::Ansi16, 255, 0, 0), "91");
+        assert_eq!(bg_sgr(ColorMode::Ansi16, 255, 0, 0), "101");
+    }
+
+    #[test]
+    fn xterm256_index_maps_exact_cube_corners() {
+        // Black and white are exact corners of the 6x6x6 color cube (index
+        // 16 and 231), not the grayscale ramp (232-255), even though nearby
+        // gray-ramp steps are also close in CIELAB distance.
+        assert_eq!(xterm256_index(0, 0, 0), 16);
+        assert_eq!(xterm256_index(255, 255, 255), 231);
+    }
+
+    #[test]
+    fn ansi16_index_round_trips_every_canonical_color() {
+        for &(r, g, b) in &ANSI16_COLORS {
+            let (digit, bright) = ansi16_index(r, g, b);
+            assert_eq!(ansi16_to_rgb(digit, bright), (r, g, b));
+        }
+    }
+
+    #[test]
+    fn quantize_for_dither_snaps_fixed_palette_result_through_color_mode() {
+        let palette = vec![(123, 45, 67)];
+        let mut dynamic = Vec::new();
+
+        let result = quantize_for_dither(
+            123.0,
+            45.0,
+            67.0,
+            Some(&palette),
+            &mut dynamic,
+            0.0,
+            ColorMode::Indexed256,
+        );
+
+        assert_eq!(result, color_mode_rgb(ColorMode::Indexed256, 123, 45, 67));
+        assert_ne!(result, (123, 45, 67));
+    }
+
+    #[test]
+    fn block_mode_does_not_requantize_already_dithered_pixels() {
+        let mut pixels = PixelGrid::new(2, 1, vec![[205, 0, 0, 255], [255, 0, 0, 255]]);
+        let config = ConversionConfig {
+            dither: true,
+            color_mode: ColorMode::Ansi16,
+            color_tolerance: 100.0,
+            use_blocks: true,
+            ..ConversionConfig::default()
+        };
+
+        dither_pixels(&mut pixels, &config, None);
+        // Dithering already snapped these to two distinct ANSI-16 colors
+        // (plain red and bright red); a tolerance this wide would merge
+        // them if `convert_blocks_mode` quantized again.
+        assert_eq!(pixels.pixel(0, 0)[..3].to_vec(), vec![205, 0, 0]);
+        assert_eq!(pixels.pixel(1, 0)[..3].to_vec(), vec![255, 0, 0]);
+
+        let out = convert_blocks_mode(&pixels, &config, "\x1b", None);
+
+        assert!(out.contains("\x1b[31m"), "{out:?}");
+        assert!(out.contains("\x1b[91m"), "{out:?}");
     }
 }
@@ -1,10 +1,44 @@
-use ansimake::{ConversionConfig, Image};
-use clap::Parser;
+use ansimake::{ColorMode, ConversionConfig, ConvolutionKernel, Image, ResampleBackend};
+use clap::{Parser, ValueEnum};
 use std::io::{self, Write};
 
+#[derive(Clone, Copy, ValueEnum)]
+enum ColorModeArg {
+    Truecolor,
+    Color256,
+    Color16,
+}
+
+impl From<ColorModeArg> for ColorMode {
+    fn from(mode: ColorModeArg) -> Self {
+        match mode {
+            ColorModeArg::Truecolor => Self::TrueColor,
+            ColorModeArg::Color256 => Self::Indexed256,
+            ColorModeArg::Color16 => Self::Ansi16,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ResampleArg {
+    Image,
+    Lanczos,
+    CatmullRom,
+}
+
+impl From<ResampleArg> for ResampleBackend {
+    fn from(backend: ResampleArg) -> Self {
+        match backend {
+            ResampleArg::Image => Self::ImageCrate,
+            ResampleArg::Lanczos => Self::Convolution(ConvolutionKernel::Lanczos3),
+            ResampleArg::CatmullRom => Self::Convolution(ConvolutionKernel::CatmullRom),
+        }
+    }
+}
+
 #[derive(Parser)]
 #[command(name = "ansimake")]
-#[command(about = "Convert PNG images to ANSI art")]
+#[command(about = "Convert PNG or QOI images to ANSI art")]
 struct Args {
     #[arg()]
     image_path: String,
@@ -23,6 +57,31 @@ struct Args {
 
     #[arg(short = 'B', long = "blocks")]
     use_blocks: bool,
+
+    #[arg(short = 'p', long = "palette")]
+    palette_size: Option<usize>,
+
+    #[arg(short = 'c', long = "color-mode", value_enum, default_value = "truecolor")]
+    color_mode: ColorModeArg,
+
+    #[arg(short = 'd', long = "dither")]
+    dither: bool,
+
+    #[arg(long = "background", value_parser = parse_background)]
+    background: Option<(u8, u8, u8)>,
+
+    #[arg(long = "resample", value_enum, default_value = "image")]
+    resample: ResampleArg,
+}
+
+fn parse_background(value: &str) -> Result<(u8, u8, u8), String> {
+    let mut parts = value.split(',');
+    let (Some(r), Some(g), Some(b), None) = (parts.next(), parts.next(), parts.next(), parts.next())
+    else {
+        return Err("expected background as r,g,b".to_string());
+    };
+    let parse = |s: &str| s.trim().parse::<u8>().map_err(|e| e.to_string());
+    Ok((parse(r)?, parse(g)?, parse(b)?))
 }
 
 fn get_terminal_size() -> (u32, u32) {
@@ -80,6 +139,11 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         alpha_threshold: 128,
         color_tolerance: args.color_tolerance,
         use_blocks: args.use_blocks,
+        palette_size: args.palette_size,
+        color_mode: args.color_mode.into(),
+        dither: args.dither,
+        background: args.background,
+        resample_backend: args.resample.into(),
         ..Default::default()
     };
     let ansi_art = img.to_ansi(&config);
@@ -0,0 +1,238 @@
+//! A minimal QOI (Quite OK Image) decoder, implemented directly against the
+//! spec so `.qoi` files can be loaded without pulling in an extra crate.
+
+use image::{DynamicImage, ImageError, RgbaImage};
+use std::io;
+
+const QOI_OP_RGB: u8 = 0xFE;
+const QOI_OP_RGBA: u8 = 0xFF;
+const HEADER_SIZE: usize = 14;
+const END_MARKER_SIZE: usize = 8;
+
+fn invalid(message: &str) -> ImageError {
+    ImageError::IoError(io::Error::new(io::ErrorKind::InvalidData, message.to_string()))
+}
+
+fn qoi_hash(pixel: [u8; 4]) -> usize {
+    let [r, g, b, a] = pixel;
+    (r as usize * 3 + g as usize * 5 + b as usize * 7 + a as usize * 11) % 64
+}
+
+fn push_pixel(pixels: &mut Vec<u8>, index: &mut [[u8; 4]; 64], pixel: [u8; 4]) {
+    pixels.extend_from_slice(&pixel);
+    index[qoi_hash(pixel)] = pixel;
+}
+
+/// Decodes a QOI byte stream into a `DynamicImage`.
+///
+/// # Errors
+///
+/// Returns an error if the header is malformed or the chunk stream is
+/// truncated or otherwise invalid.
+pub fn decode(bytes: &[u8]) -> Result<DynamicImage, ImageError> {
+    if bytes.len() < HEADER_SIZE + END_MARKER_SIZE {
+        return Err(invalid("qoi: file too short"));
+    }
+    if &bytes[0..4] != b"qoif" {
+        return Err(invalid("qoi: bad magic"));
+    }
+
+    let width = u32::from_be_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+    let height = u32::from_be_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+
+    let pixel_count = (width as usize)
+        .checked_mul(height as usize)
+        .ok_or_else(|| invalid("qoi: dimensions overflow"))?;
+
+    // The header's claimed dimensions are untrusted input -- bound them
+    // against what the chunk stream could possibly encode before trusting
+    // them for an allocation. The cheapest chunk (QOI_OP_RUN) encodes up to
+    // 62 pixels per byte, so that's the most generous ratio a legitimate
+    // file can achieve; anything claiming more than that is corrupt.
+    let available_bytes = (bytes.len() - END_MARKER_SIZE).saturating_sub(HEADER_SIZE);
+    let max_decodable_pixels = available_bytes.saturating_mul(62);
+    if pixel_count > max_decodable_pixels {
+        return Err(invalid("qoi: declared dimensions exceed what the chunk stream could encode"));
+    }
+
+    let mut pixels: Vec<u8> = Vec::with_capacity(pixel_count * 4);
+    let mut index = [[0u8; 4]; 64];
+    let mut prev = [0u8, 0, 0, 255];
+
+    let mut pos = HEADER_SIZE;
+    let end = bytes.len() - END_MARKER_SIZE;
+
+    while pos < end && pixels.len() < pixel_count * 4 {
+        let tag = bytes[pos];
+
+        if tag == QOI_OP_RGB {
+            if pos + 4 > end {
+                return Err(invalid("qoi: truncated QOI_OP_RGB chunk"));
+            }
+            prev = [bytes[pos + 1], bytes[pos + 2], bytes[pos + 3], prev[3]];
+            pos += 4;
+            push_pixel(&mut pixels, &mut index, prev);
+        } else if tag == QOI_OP_RGBA {
+            if pos + 5 > end {
+                return Err(invalid("qoi: truncated QOI_OP_RGBA chunk"));
+            }
+            prev = [bytes[pos + 1], bytes[pos + 2], bytes[pos + 3], bytes[pos + 4]];
+            pos += 5;
+            push_pixel(&mut pixels, &mut index, prev);
+        } else {
+            match tag >> 6 {
+                0b00 => {
+                    let idx = (tag & 0x3F) as usize;
+                    prev = index[idx];
+                    pos += 1;
+                    push_pixel(&mut pixels, &mut index, prev);
+                }
+                0b01 => {
+                    let dr = ((tag >> 4) & 0x03) as i8 - 2;
+                    let dg = ((tag >> 2) & 0x03) as i8 - 2;
+                    let db = (tag & 0x03) as i8 - 2;
+                    prev = [
+                        prev[0].wrapping_add_signed(dr),
+                        prev[1].wrapping_add_signed(dg),
+                        prev[2].wrapping_add_signed(db),
+                        prev[3],
+                    ];
+                    pos += 1;
+                    push_pixel(&mut pixels, &mut index, prev);
+                }
+                0b10 => {
+                    if pos + 2 > end {
+                        return Err(invalid("qoi: truncated QOI_OP_LUMA chunk"));
+                    }
+                    let dg = (tag & 0x3F) as i8 - 32;
+                    let byte2 = bytes[pos + 1];
+                    let dr_dg = ((byte2 >> 4) & 0x0F) as i8 - 8;
+                    let db_dg = (byte2 & 0x0F) as i8 - 8;
+                    prev = [
+                        prev[0].wrapping_add_signed(dg.wrapping_add(dr_dg)),
+                        prev[1].wrapping_add_signed(dg),
+                        prev[2].wrapping_add_signed(dg.wrapping_add(db_dg)),
+                        prev[3],
+                    ];
+                    pos += 2;
+                    push_pixel(&mut pixels, &mut index, prev);
+                }
+                _ => {
+                    let run = (tag & 0x3F) + 1;
+                    pos += 1;
+                    for _ in 0..run {
+                        push_pixel(&mut pixels, &mut index, prev);
+                    }
+                }
+            }
+        }
+    }
+
+    if pixels.len() < pixel_count * 4 {
+        return Err(invalid("qoi: chunk stream ended before all pixels were decoded"));
+    }
+
+    let image = RgbaImage::from_raw(width, height, pixels)
+        .ok_or_else(|| invalid("qoi: pixel buffer does not match declared dimensions"))?;
+    Ok(DynamicImage::ImageRgba8(image))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(HEADER_SIZE);
+        bytes.extend_from_slice(b"qoif");
+        bytes.extend_from_slice(&width.to_be_bytes());
+        bytes.extend_from_slice(&height.to_be_bytes());
+        bytes.push(4); // channels: RGBA
+        bytes.push(0); // colorspace: sRGB
+        bytes
+    }
+
+    #[test]
+    fn round_trips_a_tiny_hand_encoded_image() {
+        let mut bytes = header(2, 1);
+        bytes.extend_from_slice(&[QOI_OP_RGB, 10, 20, 30]);
+        bytes.extend_from_slice(&[QOI_OP_RGB, 40, 50, 60]);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let image = decode(&bytes).expect("valid qoi stream should decode");
+        let rgba = image.to_rgba8();
+        assert_eq!(rgba.get_pixel(0, 0).0, [10, 20, 30, 255]);
+        assert_eq!(rgba.get_pixel(1, 0).0, [40, 50, 60, 255]);
+    }
+
+    #[test]
+    fn rejects_dimensions_the_byte_stream_cannot_possibly_encode() {
+        let mut bytes = header(0xFFFF, 0xFFFF);
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        assert!(decode(&bytes).is_err());
+    }
+
+    #[test]
+    fn qoi_op_index_replays_a_cached_pixel() {
+        // qoi_hash([10, 20, 30, 255]) == 9, so a QOI_OP_INDEX chunk tagged
+        // with index 9 must replay that exact pixel.
+        let mut bytes = header(2, 1);
+        bytes.extend_from_slice(&[QOI_OP_RGB, 10, 20, 30]);
+        bytes.push(0b00_001001); // QOI_OP_INDEX, idx = 9
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let image = decode(&bytes).expect("valid qoi stream should decode");
+        let rgba = image.to_rgba8();
+        assert_eq!(rgba.get_pixel(0, 0).0, [10, 20, 30, 255]);
+        assert_eq!(rgba.get_pixel(1, 0).0, [10, 20, 30, 255]);
+    }
+
+    #[test]
+    fn qoi_op_diff_applies_in_range_deltas() {
+        // dr = 1, dg = 0, db = -1, each biased by +2 into the 2-bit fields.
+        let mut bytes = header(2, 1);
+        bytes.extend_from_slice(&[QOI_OP_RGB, 100, 100, 100]);
+        bytes.push(0b01_11_10_01); // QOI_OP_DIFF, dr=3(+2), dg=2(+2), db=1(+2)
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let image = decode(&bytes).expect("valid qoi stream should decode");
+        let rgba = image.to_rgba8();
+        assert_eq!(rgba.get_pixel(0, 0).0, [100, 100, 100, 255]);
+        assert_eq!(rgba.get_pixel(1, 0).0, [101, 100, 99, 255]);
+    }
+
+    #[test]
+    fn qoi_op_luma_applies_in_range_deltas() {
+        // dg = 10 (biased +32 = 42), dr-dg = -3 (biased +8 = 5), db-dg = 5
+        // (biased +8 = 13), giving dr = 7, dg = 10, db = 15.
+        let mut bytes = header(2, 1);
+        bytes.extend_from_slice(&[QOI_OP_RGB, 50, 50, 50]);
+        bytes.extend_from_slice(&[0b10_101010, 0b0101_1101]); // QOI_OP_LUMA
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let image = decode(&bytes).expect("valid qoi stream should decode");
+        let rgba = image.to_rgba8();
+        assert_eq!(rgba.get_pixel(0, 0).0, [50, 50, 50, 255]);
+        assert_eq!(rgba.get_pixel(1, 0).0, [57, 60, 65, 255]);
+    }
+
+    #[test]
+    fn qoi_op_run_repeats_the_previous_pixel() {
+        // A run of 5 (biased -1 into the 6-bit length) must emit the
+        // previous pixel 5 times in a row.
+        let mut bytes = header(6, 1);
+        bytes.extend_from_slice(&[QOI_OP_RGB, 1, 2, 3]);
+        bytes.push(0b11_000100); // QOI_OP_RUN, run = 5
+        bytes.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let image = decode(&bytes).expect("valid qoi stream should decode");
+        let rgba = image.to_rgba8();
+        for x in 0..6 {
+            assert_eq!(
+                rgba.get_pixel(x, 0).0,
+                [1, 2, 3, 255],
+                "pixel {x} should repeat the run color"
+            );
+        }
+    }
+}